@@ -0,0 +1,156 @@
+//! Parses `/proc/interrupts` so IRQs can be resolved by the NIC and queue
+//! they serve instead of by their (reboot-unstable) number.
+
+use serde::Deserialize;
+use std::path::Path;
+
+/// One data line from `/proc/interrupts`: an IRQ number and the trailing
+/// descriptor that (driver permitting) names the device and queue it serves,
+/// e.g. `eth0-TxRx-3` or `mlx5_comp12@pci:0000:01:00.0`.
+pub struct Interrupt {
+    pub irq: u32,
+    pub descriptor: String,
+}
+
+impl Interrupt {
+    /// Returns true if this interrupt's descriptor names `nic` and, when
+    /// `kind`/`queue` are given, also matches the trailing `-<kind>-<queue>`
+    /// suffix.
+    pub fn matches(&self, nic: &str, kind: Option<QueueKind>, queue: Option<usize>) -> bool {
+        if !Self::names(&self.descriptor, nic) {
+            return false;
+        }
+
+        if kind.is_none() && queue.is_none() {
+            return true;
+        }
+
+        match parse_queue_suffix(&self.descriptor) {
+            Some((found_kind, found_queue)) => {
+                kind.map(|k| k == found_kind).unwrap_or(true)
+                    && queue.map(|q| q == found_queue).unwrap_or(true)
+            }
+            None => false,
+        }
+    }
+
+    /// The queue index parsed from this interrupt's descriptor suffix, if it
+    /// has one (e.g. `3` for `eth0-TxRx-3`).
+    pub fn queue_index(&self) -> Option<usize> {
+        parse_queue_suffix(&self.descriptor).map(|(_, index)| index)
+    }
+
+    // True if `descriptor` names `nic` on a delimiter boundary, so a rule for
+    // "eth1" doesn't also match "eth10-TxRx-0" or "eth1.100" (a VLAN
+    // sub-interface, which is a distinct NIC from "eth1" itself).
+    fn names(descriptor: &str, nic: &str) -> bool {
+        descriptor == nic
+            || descriptor.starts_with(&format!("{nic}-"))
+            || descriptor.starts_with(&format!("{nic}@"))
+    }
+}
+
+/// The kind of queue an IRQ services, as named in its descriptor suffix.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueKind {
+    #[default]
+    Combined,
+    Rx,
+    Tx,
+}
+
+// Descriptors for multi-queue NICs end in "-<kind>-<index>", e.g. the "TxRx-3"
+// in "eth0-TxRx-3". Descriptors without that shape (bare driver names, PCI
+// addresses) have no resolvable kind/queue and only match unqualified rules.
+fn parse_queue_suffix(descriptor: &str) -> Option<(QueueKind, usize)> {
+    let (head, index) = descriptor.rsplit_once('-')?;
+    let index: usize = index.parse().ok()?;
+    let (_, kind) = head.rsplit_once('-')?;
+
+    let kind = match kind.to_ascii_lowercase().as_str() {
+        "txrx" | "combined" => QueueKind::Combined,
+        "tx" => QueueKind::Tx,
+        "rx" => QueueKind::Rx,
+        _ => return None,
+    };
+
+    Some((kind, index))
+}
+
+/// Reads and parses `/proc/interrupts`.
+pub fn read() -> std::io::Result<Vec<Interrupt>> {
+    Ok(parse(&std::fs::read_to_string(Path::new(
+        "/proc/interrupts",
+    ))?))
+}
+
+/// Parses the body of `/proc/interrupts`. Tolerates the leading whitespace on
+/// the right-justified IRQ column and skips rows whose IRQ column isn't
+/// numeric (the CPU header line, and counters like `NMI`/`LOC`).
+pub fn parse(text: &str) -> Vec<Interrupt> {
+    let mut interrupts = Vec::new();
+
+    for line in text.lines() {
+        let Some((irq, rest)) = line.split_once(':') else {
+            continue;
+        };
+
+        let Ok(irq) = irq.trim().parse::<u32>() else {
+            continue;
+        };
+
+        let Some(descriptor) = rest.split_whitespace().last() else {
+            continue;
+        };
+
+        interrupts.push(Interrupt {
+            irq,
+            descriptor: descriptor.to_string(),
+        });
+    }
+
+    interrupts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tolerates_the_right_justified_irq_column() {
+        let text = "           CPU0       CPU1\n\
+ 32:          0          0   IR-PCI-MSI 1048576-edge      eth0-TxRx-0\n\
+131:          0          0   IR-PCI-MSI 2097152-edge      eth0-TxRx-1\n";
+
+        let interrupts = parse(text);
+
+        assert_eq!(interrupts.len(), 2);
+        assert_eq!(interrupts[0].irq, 32);
+        assert_eq!(interrupts[0].descriptor, "eth0-TxRx-0");
+        assert_eq!(interrupts[1].irq, 131);
+        assert_eq!(interrupts[1].descriptor, "eth0-TxRx-1");
+    }
+
+    #[test]
+    fn parse_skips_rows_without_a_numeric_irq_column() {
+        let text = "           CPU0       CPU1\n\
+ 32:          0          0   IR-PCI-MSI 1048576-edge      eth0-TxRx-0\n\
+NMI:          0          0   Non-maskable interrupts\n\
+LOC:    1234567    1234568   Local timer interrupts\n";
+
+        let interrupts = parse(text);
+
+        assert_eq!(interrupts.len(), 1);
+        assert_eq!(interrupts[0].irq, 32);
+    }
+
+    #[test]
+    fn matches_requires_a_delimiter_boundary() {
+        let irq = |descriptor: &str| Interrupt { irq: 0, descriptor: descriptor.to_string() };
+
+        assert!(irq("eth1-TxRx-0").matches("eth1", None, None));
+        assert!(!irq("eth10-TxRx-0").matches("eth1", None, None));
+        assert!(!irq("eth1.100").matches("eth1", None, None));
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::io::Write;
 use std::fmt::Display;
 use std::path::PathBuf;
@@ -5,35 +6,88 @@ use clap::value_parser;
 use clap::Command;
 use std::path::Path;
 use std::sync::Arc;
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use figment::Figment;
+use figment::providers::{Env, Format, Toml};
+
+mod interrupts;
+mod numa;
+
+use interrupts::QueueKind;
+
+// The CONFIG and PROFILE positionals are shared by every subcommand.
+fn config_arg() -> clap::Arg {
+    clap::Arg::new("CONFIG")
+        .help("Pinball configuration file")
+        .value_parser(value_parser!(PathBuf))
+        .action(clap::ArgAction::Set)
+        .required(true)
+        .index(1)
+}
+
+fn profile_arg() -> clap::Arg {
+    clap::Arg::new("PROFILE")
+        .help("Pinball profile name")
+        .value_parser(value_parser!(String))
+        .action(clap::ArgAction::Set)
+        .required(true)
+        .index(2)
+}
 
 fn main() {
     // parse command line options
     let cli = Command::new(env!("CARGO_BIN_NAME"))
         .version(env!("CARGO_PKG_VERSION"))
         .long_about("Rezolus provides high-resolution systems performance telemetry.")
-        .subcommand_negates_reqs(true)
-        .arg(
-            clap::Arg::new("CONFIG")
-                .help("Pinball configuration file")
-                .value_parser(value_parser!(PathBuf))
-                .action(clap::ArgAction::Set)
-                .required(true)
-                .index(1),
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("apply")
+                .about("Apply the profile to the live system")
+                .arg(config_arg())
+                .arg(profile_arg()),
+        )
+        .subcommand(
+            Command::new("plan")
+                .about("Print the ethtool invocations and sysfs writes the profile would make, without changing anything")
+                .arg(config_arg())
+                .arg(profile_arg()),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Read back the current NIC state and show only what the profile would change")
+                .arg(config_arg())
+                .arg(profile_arg()),
         )
-        .arg(
-            clap::Arg::new("PROFILE")
-                .help("Pinball profile name")
-                .value_parser(value_parser!(String))
-                .action(clap::ArgAction::Set)
-                .required(true)
-                .index(2),
+        .subcommand(
+            Command::new("revert")
+                .about("Replay a snapshot written by `apply`, restoring the state it captured")
+                .arg(
+                    clap::Arg::new("SNAPSHOT")
+                        .help("Snapshot file written by a previous `apply`")
+                        .value_parser(value_parser!(PathBuf))
+                        .action(clap::ArgAction::Set)
+                        .required(true)
+                        .index(1),
+                ),
         )
         .get_matches();
 
-    let config_path: PathBuf = cli.get_one::<PathBuf>("CONFIG").unwrap().to_path_buf();
-    let profile_name: String = cli.get_one::<String>("PROFILE").unwrap().to_string();
+    let (subcommand, matches) = cli.subcommand().expect("a subcommand is required");
+
+    if subcommand == "revert" {
+        let snapshot_path: PathBuf = matches.get_one::<PathBuf>("SNAPSHOT").unwrap().to_path_buf();
+
+        let snapshot = Snapshot::load(&snapshot_path).unwrap_or_else(|e| {
+            eprintln!("unable to load snapshot {:?}: {e}", snapshot_path);
+            std::process::exit(1);
+        });
+
+        snapshot.revert();
+        return;
+    }
+
+    let config_path: PathBuf = matches.get_one::<PathBuf>("CONFIG").unwrap().to_path_buf();
+    let profile_name: String = matches.get_one::<String>("PROFILE").unwrap().to_string();
 
     let config: Arc<Config> = {
         println!("loading config: {:?}", config_path);
@@ -51,52 +105,211 @@ fn main() {
         std::process::exit(1);
     });
 
-    for nic in &profile.network_interface {
-        nic.configure();
-    }
+    match subcommand {
+        "apply" => {
+            let snapshot = Snapshot::capture(profile);
+
+            match snapshot.save(&config_path, &profile_name) {
+                Ok(path) => println!("wrote snapshot: {:?}", path),
+                Err(e) => {
+                    eprintln!("unable to write snapshot, refusing to apply: {e}");
+                    std::process::exit(1);
+                }
+            }
 
+            for nic in profile.network_interface.values() {
+                nic.configure();
+            }
+        }
+        "plan" => {
+            for nic in profile.network_interface.values() {
+                nic.plan();
+            }
+        }
+        "diff" => {
+            for nic in profile.network_interface.values() {
+                nic.diff();
+            }
+        }
+        _ => unreachable!("clap guarantees one of the defined subcommands"),
+    }
 }
 
 #[derive(Deserialize, Default)]
 pub struct Config {
-    profile: Vec<Profile>,
+    profile: BTreeMap<String, Profile>,
 }
 
 impl Config {
-    pub fn load(path: &dyn AsRef<Path>) -> Result<Self, String> {
-        let content = std::fs::read_to_string(path)
-            .map_err(|e| {
-                eprintln!("unable to open config file: {e}");
-                std::process::exit(1);
-            })
-            .unwrap();
+    // Loads the config by merging the TOML file with a `PINBALL_*` environment
+    // layer, so a single templated file can be tuned per host without being
+    // edited. Environment keys use `__` to address nested fields, e.g.
+    // `PINBALL_PROFILE__default__NETWORK_INTERFACE__eth0__QUEUES__COMBINED=16`.
+    // `profile` and `network_interface` are keyed by name rather than indexed,
+    // since figment's `Env` provider only ever nests `__`-separated segments
+    // into dicts, never arrays — a `Vec` field here would mean the env layer
+    // replaces rather than merges with the TOML-provided list.
+    // Any parse or type error is returned with its offending key path rather
+    // than exiting the process, so callers can format and handle it.
+    pub fn load(path: &dyn AsRef<Path>) -> Result<Self, Box<figment::Error>> {
+        let path = path.as_ref();
 
-        let config: Config = toml::from_str(&content)
-            .map_err(|e| {
-                eprintln!("failed to parse config file: {e}");
-                std::process::exit(1);
-            })
-            .unwrap();
+        if !path.exists() {
+            return Err(Box::new(format!("config file not found: {}", path.display()).into()));
+        }
+
+        let mut config: Config = Figment::new()
+            .merge(Toml::file(path))
+            .merge(Env::prefixed("PINBALL_").split("__"))
+            .extract()
+            .map_err(Box::new)?;
+
+        for profile in config.profile.values_mut() {
+            for (nic_name, nic) in &mut profile.network_interface {
+                nic.name = nic_name.clone();
+            }
+        }
 
         Ok(config)
     }
 
     pub fn profile(&self, name: &str) -> Option<&Profile> {
-        self.profile.iter().find(|&profile| profile.name == name)
+        self.profile.get(name)
+    }
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+
+    #[test]
+    fn env_overrides_a_value_inside_the_profile_table() {
+        let path = std::env::temp_dir().join(format!("pinball-test-{}.toml", std::process::id()));
+
+        std::fs::write(
+            &path,
+            "[profile.default.network_interface.eth0]\nirqs = []\n\n\
+             [profile.default.network_interface.eth0.queues]\ncombined = 4\n",
+        )
+        .unwrap();
+        std::env::set_var("PINBALL_PROFILE__default__NETWORK_INTERFACE__eth0__QUEUES__COMBINED", "16");
+
+        let result = Config::load(&path);
+
+        std::env::remove_var("PINBALL_PROFILE__default__NETWORK_INTERFACE__eth0__QUEUES__COMBINED");
+        std::fs::remove_file(&path).ok();
+
+        let config = result.unwrap();
+        let nic = config.profile("default").unwrap().network_interface.get("eth0").unwrap();
+
+        assert_eq!(nic.name, "eth0");
+        assert_eq!(nic.queues.combined, Some(16));
     }
 }
 
 #[derive(Deserialize, Default)]
 pub struct Profile {
-    name: String,
-    network_interface: Vec<NetworkInterface>,
+    network_interface: BTreeMap<String, NetworkInterface>,
 }
 
 #[derive(Deserialize, Default)]
 pub struct NetworkInterface {
+    // populated from the `network_interface` table's key after load, not read from TOML
+    #[serde(default)]
     name: String,
     queues: NetworkQueues,
-    irqs: HashMap<String, String>,
+    ring: Option<RingBuffers>,
+    coalescing: Option<Coalescing>,
+    offloads: Option<Offloads>,
+    steering: Option<SoftwareSteering>,
+    irqs: Vec<IrqRule>,
+}
+
+/// Matches one or more IRQs by NIC name and, optionally, queue kind/index, and
+/// gives the affinity they should all be pinned to. Resolved against
+/// `/proc/interrupts` at apply time rather than against a fixed IRQ number, so
+/// the mapping survives reboots and driver reloads.
+#[derive(Deserialize, Default)]
+pub struct IrqRule {
+    kind: Option<QueueKind>,
+    queue: Option<usize>,
+    // an explicit CPU list, e.g. "0-3,8"; mutually exclusive with `auto`
+    affinity: Option<String>,
+    // compute affinity from NUMA topology instead of a fixed list
+    auto: Option<AutoAffinity>,
+}
+
+/// Assigns IRQs round-robin across the CPUs local to the NIC's NUMA node,
+/// instead of a hand-maintained CPU list.
+#[derive(Deserialize, Default)]
+pub struct AutoAffinity {
+    // skip SMT siblings, pinning only to one CPU per physical core
+    #[serde(default)]
+    physical_only: bool,
+}
+
+impl IrqRule {
+    // Resolve this rule against the currently live IRQs for `nic`, sorted by
+    // queue index, erroring clearly if nothing matched so a typo in the
+    // pattern doesn't silently no-op.
+    fn resolve(
+        &self,
+        nic: &str,
+        interrupts: &[interrupts::Interrupt],
+    ) -> Result<Vec<(u32, Option<usize>)>, String> {
+        let mut matched: Vec<(u32, Option<usize>)> = interrupts
+            .iter()
+            .filter(|i| i.matches(nic, self.kind, self.queue))
+            .map(|i| (i.irq, i.queue_index()))
+            .collect();
+
+        if matched.is_empty() {
+            return Err(format!(
+                "no /proc/interrupts entries matched nic={nic} kind={:?} queue={:?}",
+                self.kind, self.queue
+            ));
+        }
+
+        matched.sort_by_key(|(_, queue)| queue.unwrap_or(usize::MAX));
+
+        Ok(matched)
+    }
+
+    // Pair each resolved IRQ with the affinity it should get: either this
+    // rule's explicit CPU list, or one CPU from the NIC's local NUMA node,
+    // assigned round-robin in queue order.
+    fn assign(&self, nic: &str, matched: Vec<(u32, Option<usize>)>) -> Vec<(u32, String)> {
+        match &self.auto {
+            Some(auto) => {
+                let cpus = numa::local_cpus(nic, auto.physical_only).unwrap_or_else(|e| {
+                    eprintln!("unable to determine NUMA-local CPUs for {nic}: {e}");
+                    std::process::exit(1);
+                });
+
+                if cpus.is_empty() {
+                    eprintln!("no candidate CPUs found for {nic}'s NUMA node");
+                    std::process::exit(1);
+                }
+
+                matched
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (irq, _))| (irq, cpus[i % cpus.len()].to_string()))
+                    .collect()
+            }
+            None => {
+                let affinity = self.affinity.clone().unwrap_or_else(|| {
+                    eprintln!("IRQ rule for {nic} has neither `affinity` nor `auto` set");
+                    std::process::exit(1);
+                });
+
+                matched
+                    .into_iter()
+                    .map(|(irq, _)| (irq, affinity.clone()))
+                    .collect()
+            }
+        }
+    }
 }
 
 impl NetworkInterface {
@@ -104,40 +317,319 @@ impl NetworkInterface {
         println!("configuring IRQs for: {}", self.name);
         println!("setting queues to: {}", self.queues);
         self.configure_queues();
+
+        if let Some(ring) = &self.ring {
+            println!("setting ring buffers to: {ring}");
+            ring.apply(&self.name);
+        }
+
+        if let Some(coalescing) = &self.coalescing {
+            println!("setting interrupt coalescing to: {coalescing}");
+            coalescing.apply(&self.name);
+        }
+
+        if let Some(offloads) = &self.offloads {
+            println!("setting offloads to: {offloads}");
+            offloads.apply(&self.name);
+        }
+
+        if let Some(steering) = &self.steering {
+            println!("setting software steering");
+            steering.apply(&self.name);
+        }
+
         println!("setting IRQ affinity");
         self.set_irq_affinity();
     }
 
+    // Prints every ethtool invocation and sysfs write the profile describes,
+    // without touching the live system. Current state is not read, so this
+    // also lists writes that `configure()` skips because the value already
+    // matches; `diff()` shows only what would change.
+    pub fn plan(&self) {
+        println!("# {}", self.name);
+        self.queues.plan(&self.name);
+
+        if let Some(ring) = &self.ring {
+            ring.plan(&self.name);
+        }
+
+        if let Some(coalescing) = &self.coalescing {
+            coalescing.plan(&self.name);
+        }
+
+        if let Some(offloads) = &self.offloads {
+            offloads.plan(&self.name);
+        }
+
+        if let Some(steering) = &self.steering {
+            steering.plan(&self.name);
+        }
+
+        self.plan_irq_affinity();
+    }
+
+    // Reads back the current queue counts and IRQ affinity and prints only
+    // what the profile would change.
+    pub fn diff(&self) {
+        println!("# {}", self.name);
+        self.queues.diff(&self.name);
+
+        if let Some(ring) = &self.ring {
+            ring.diff(&self.name);
+        }
+
+        if let Some(coalescing) = &self.coalescing {
+            coalescing.diff(&self.name);
+        }
+
+        if let Some(offloads) = &self.offloads {
+            offloads.diff(&self.name);
+        }
+
+        if let Some(steering) = &self.steering {
+            steering.diff(&self.name);
+        }
+
+        self.diff_irq_affinity();
+    }
+
     fn configure_queues(&self) {
         self.queues.apply(&self.name);
     }
 
-    fn set_irq_affinity(&self) {
-        for (irq, affinity) in self.irqs.iter() {
-            let irq: u32 = irq.parse().expect("failed to parse");
+    // Resolve every IRQ rule against the current /proc/interrupts and assign
+    // each matched IRQ its affinity, exiting with the rule's error if a
+    // pattern matched nothing.
+    fn resolved_irqs(&self) -> Vec<(u32, String)> {
+        let interrupts = interrupts::read().unwrap_or_else(|e| {
+            eprintln!("unable to read /proc/interrupts: {e}");
+            std::process::exit(1);
+        });
+
+        self.irqs
+            .iter()
+            .flat_map(|rule| {
+                let matched = rule.resolve(&self.name, &interrupts).unwrap_or_else(|e| {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                });
+
+                rule.assign(&self.name, matched)
+            })
+            .collect()
+    }
+
+    fn plan_irq_affinity(&self) {
+        for (irq, affinity) in self.resolved_irqs() {
+            println!("write \"{affinity}\" to /proc/irq/{irq}/smp_affinity_list");
+        }
+    }
 
-            // validate the affinity list doesn't contain anything funky
-            assert!(affinity.bytes().all(|b| b.is_ascii_digit() || b == b"-"[0] || b == b","[0]));
+    fn diff_irq_affinity(&self) {
+        for (irq, affinity) in self.resolved_irqs() {
+            let path = format!("/proc/irq/{irq}/smp_affinity_list");
 
-            for i in 0..5 {
-                if let Ok(mut f) = std::fs::File::options().write(true).truncate(true).create(false).open(format!("/proc/irq/{irq}/smp_affinity_list")) {
-                    if f.write_all(affinity.as_bytes()).is_ok() {
-                        break;
+            match std::fs::read_to_string(&path) {
+                Ok(current) => {
+                    let current = current.trim();
+
+                    if current != affinity {
+                        println!("{path}: {current} -> {affinity}");
                     }
                 }
+                Err(e) => {
+                    eprintln!("unable to read {path}: {e}");
+                }
+            }
+        }
+    }
 
-                std::thread::sleep(core::time::Duration::from_millis(100));
+    fn set_irq_affinity(&self) {
+        for (irq, affinity) in self.resolved_irqs() {
+            apply_irq_affinity(irq, &affinity);
+        }
+    }
+}
 
-                if i == 4 {
-                    eprintln!("failed to set irq: {irq} smp affinity list: {affinity}");
-                    std::process::exit(1);
-                }
+// Writes `affinity` to the IRQ's smp_affinity_list, skipping the write if the
+// current value already matches so repeated runs are no-ops.
+fn apply_irq_affinity(irq: u32, affinity: &str) {
+    // validate the affinity list doesn't contain anything funky
+    assert!(affinity.bytes().all(|b| b.is_ascii_digit() || b == b"-"[0] || b == b","[0]));
+
+    let path = format!("/proc/irq/{irq}/smp_affinity_list");
+
+    if std::fs::read_to_string(&path).map(|s| s.trim() == affinity).unwrap_or(false) {
+        return;
+    }
+
+    for i in 0..5 {
+        if let Ok(mut f) = std::fs::File::options().write(true).truncate(true).create(false).open(&path) {
+            if f.write_all(affinity.as_bytes()).is_ok() {
+                return;
             }
         }
+
+        std::thread::sleep(core::time::Duration::from_millis(100));
+
+        if i == 4 {
+            eprintln!("failed to set irq: {irq} smp affinity list: {affinity}");
+            std::process::exit(1);
+        }
     }
 }
 
-#[derive(Deserialize, Default)]
+/// A point-in-time capture of the NIC state a profile is about to change,
+/// written before `apply` so a bad profile can be undone with `revert`.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Snapshot {
+    interfaces: Vec<InterfaceSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct InterfaceSnapshot {
+    name: String,
+    queues: NetworkQueues,
+    ring: Option<RingBuffers>,
+    coalescing: Option<Coalescing>,
+    offloads: Option<Offloads>,
+    // (sysfs path, current contents) for every rps_cpus/xps_cpus file the profile touches
+    steering: Vec<(String, String)>,
+    // (irq, current smp_affinity_list) for every IRQ the profile touches
+    irqs: Vec<(u32, String)>,
+}
+
+impl Snapshot {
+    // Read back the current state of everything `profile` is about to change
+    pub fn capture(profile: &Profile) -> Self {
+        Snapshot {
+            interfaces: profile.network_interface.values().map(InterfaceSnapshot::capture).collect(),
+        }
+    }
+
+    // Serialize the snapshot to a timestamped file next to the config
+    pub fn save(&self, config_path: &Path, profile_name: &str) -> std::io::Result<PathBuf> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let config_name = config_path.file_name().and_then(|n| n.to_str()).unwrap_or("pinball");
+        let path = config_path.with_file_name(format!("{config_name}.{profile_name}.{timestamp}.snapshot.json"));
+
+        let json = serde_json::to_string_pretty(self).expect("failed to serialize snapshot");
+        std::fs::write(&path, json)?;
+
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+
+        serde_json::from_str(&content)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    // Replay the captured values through the same apply machinery used by `apply`
+    pub fn revert(&self) {
+        for interface in &self.interfaces {
+            interface.revert();
+        }
+    }
+}
+
+impl InterfaceSnapshot {
+    fn capture(nic: &NetworkInterface) -> Self {
+        let queues = NetworkQueues::current(&nic.name).unwrap_or_else(|e| {
+            eprintln!("unable to snapshot queues for {}: {e}", nic.name);
+            NetworkQueues::default()
+        });
+
+        let ring = nic.ring.as_ref().map(|_| {
+            RingBuffers::current(&nic.name).unwrap_or_else(|e| {
+                eprintln!("unable to snapshot ring buffers for {}: {e}", nic.name);
+                RingBuffers::default()
+            })
+        });
+
+        let coalescing = nic.coalescing.as_ref().map(|_| {
+            Coalescing::current(&nic.name).unwrap_or_else(|e| {
+                eprintln!("unable to snapshot coalescing settings for {}: {e}", nic.name);
+                Coalescing::default()
+            })
+        });
+
+        let offloads = nic.offloads.as_ref().map(|_| {
+            Offloads::current(&nic.name).unwrap_or_else(|e| {
+                eprintln!("unable to snapshot offload settings for {}: {e}", nic.name);
+                Offloads::default()
+            })
+        });
+
+        let mut steering = Vec::new();
+
+        if let Some(config) = &nic.steering {
+            if config.rps_cpus.is_some() {
+                steering.extend(SoftwareSteering::current(&nic.name, "rx", "rps_cpus"));
+            }
+
+            if config.xps_cpus.is_some() {
+                steering.extend(SoftwareSteering::current(&nic.name, "tx", "xps_cpus"));
+            }
+        }
+
+        let irqs = nic
+            .resolved_irqs()
+            .into_iter()
+            .map(|(irq, _)| {
+                let affinity = std::fs::read_to_string(format!("/proc/irq/{irq}/smp_affinity_list"))
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+
+                (irq, affinity)
+            })
+            .collect();
+
+        InterfaceSnapshot {
+            name: nic.name.clone(),
+            queues,
+            ring,
+            coalescing,
+            offloads,
+            steering,
+            irqs,
+        }
+    }
+
+    fn revert(&self) {
+        println!("reverting: {}", self.name);
+        self.queues.apply(&self.name);
+
+        if let Some(ring) = &self.ring {
+            ring.apply(&self.name);
+        }
+
+        if let Some(coalescing) = &self.coalescing {
+            coalescing.apply(&self.name);
+        }
+
+        if let Some(offloads) = &self.offloads {
+            offloads.apply(&self.name);
+        }
+
+        for (path, mask) in &self.steering {
+            SoftwareSteering::write_mask(path, mask);
+        }
+
+        for (irq, affinity) in &self.irqs {
+            apply_irq_affinity(*irq, affinity);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
 pub struct NetworkQueues {
     transmit: Option<usize>,
     receive: Option<usize>,
@@ -145,20 +637,132 @@ pub struct NetworkQueues {
 }
 
 impl NetworkQueues {
-    // Apply the queue configuration to the specified network interface
+    // Apply the queue configuration to the specified network interface,
+    // skipping fields whose current value already matches the target
     pub fn apply(&self, nic: &str) {
         // we're about to use this in a command, make sure there's no way to
         // escape and run something else
         assert!(nic.bytes().all(|b| b.is_ascii_alphanumeric()));
 
+        let args = self.changed_args(nic);
+
+        if args.is_empty() {
+            return;
+        }
+
         std::process::Command::new("/usr/sbin/ethtool")
             .arg("-L")
             .arg(nic)
-            .args(self.args())
+            .args(args)
             .output()
             .expect("failed to execute process");
     }
 
+    // Like `args()`, but only for fields whose current value differs from the target
+    fn changed_args(&self, nic: &str) -> Vec<String> {
+        let current = Self::current(nic).unwrap_or_default();
+        let mut r = Vec::new();
+
+        if let Some(tx) = self.transmit {
+            if current.transmit != Some(tx) {
+                r.push("tx".into());
+                r.push(format!("{tx}"));
+            }
+        }
+
+        if let Some(rx) = self.receive {
+            if current.receive != Some(rx) {
+                r.push("rx".into());
+                r.push(format!("{rx}"));
+            }
+        }
+
+        if let Some(combined) = self.combined {
+            if current.combined != Some(combined) {
+                r.push("combined".into());
+                r.push(format!("{combined}"));
+            }
+        }
+
+        r
+    }
+
+    // Print the exact ethtool invocation this config would run, without running it
+    pub fn plan(&self, nic: &str) {
+        let args = self.args();
+
+        if args.is_empty() {
+            return;
+        }
+
+        println!("/usr/sbin/ethtool -L {nic} {}", args.join(" "));
+    }
+
+    // Read back the current channel counts and print only what would change
+    pub fn diff(&self, nic: &str) {
+        let current = match Self::current(nic) {
+            Ok(current) => current,
+            Err(e) => {
+                eprintln!("unable to read current queue counts for {nic}: {e}");
+                return;
+            }
+        };
+
+        if self.transmit.is_some() && self.transmit != current.transmit {
+            println!("{nic} tx: {:?} -> {:?}", current.transmit, self.transmit);
+        }
+
+        if self.receive.is_some() && self.receive != current.receive {
+            println!("{nic} rx: {:?} -> {:?}", current.receive, self.receive);
+        }
+
+        if self.combined.is_some() && self.combined != current.combined {
+            println!("{nic} combined: {:?} -> {:?}", current.combined, self.combined);
+        }
+    }
+
+    // Read the current channel counts for `nic` from `ethtool -l`
+    fn current(nic: &str) -> Result<NetworkQueues, String> {
+        assert!(nic.bytes().all(|b| b.is_ascii_alphanumeric()));
+
+        let output = std::process::Command::new("/usr/sbin/ethtool")
+            .arg("-l")
+            .arg(nic)
+            .output()
+            .map_err(|e| format!("failed to execute process: {e}"))?;
+
+        Ok(Self::parse_current(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    // Parse the "Current hardware settings" section of `ethtool -l` output
+    fn parse_current(text: &str) -> NetworkQueues {
+        let mut queues = NetworkQueues::default();
+        let mut in_current_settings = false;
+
+        for line in text.lines() {
+            let line = line.trim();
+
+            if line.starts_with("Current hardware settings") {
+                in_current_settings = true;
+                continue;
+            }
+
+            if !in_current_settings {
+                continue;
+            }
+
+            if let Some(value) = line.strip_prefix("RX:") {
+                queues.receive = value.trim().parse().ok();
+            } else if let Some(value) = line.strip_prefix("TX:") {
+                queues.transmit = value.trim().parse().ok();
+            } else if let Some(value) = line.strip_prefix("Combined:") {
+                queues.combined = value.trim().parse().ok();
+            }
+        }
+
+        queues
+    }
+
     // Turn the config into a set of args to pass to ethtool
     fn args(&self) -> Vec<String> {
         let mut r = Vec::new();
@@ -187,4 +791,544 @@ impl Display for NetworkQueues {
         let s = self.args().join(" ");
         write!(f, "{s}")
     }
+}
+
+// Renders an on/off ethtool argument value
+fn on_off(value: bool) -> String {
+    if value { "on" } else { "off" }.into()
+}
+
+// Parses an on/off ethtool report value back into a bool
+fn parse_on_off(value: &str) -> Option<bool> {
+    match value {
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct RingBuffers {
+    rx: Option<usize>,
+    tx: Option<usize>,
+}
+
+impl RingBuffers {
+    // Apply the ring buffer sizes to the specified network interface,
+    // skipping fields whose current value already matches the target
+    pub fn apply(&self, nic: &str) {
+        assert!(nic.bytes().all(|b| b.is_ascii_alphanumeric()));
+
+        let args = self.changed_args(nic);
+
+        if args.is_empty() {
+            return;
+        }
+
+        std::process::Command::new("/usr/sbin/ethtool")
+            .arg("-G")
+            .arg(nic)
+            .args(args)
+            .output()
+            .expect("failed to execute process");
+    }
+
+    // Like `args()`, but only for fields whose current value differs from the target
+    fn changed_args(&self, nic: &str) -> Vec<String> {
+        let current = Self::current(nic).unwrap_or_default();
+        let mut r = Vec::new();
+
+        if let Some(rx) = self.rx {
+            if current.rx != Some(rx) {
+                r.push("rx".into());
+                r.push(format!("{rx}"));
+            }
+        }
+
+        if let Some(tx) = self.tx {
+            if current.tx != Some(tx) {
+                r.push("tx".into());
+                r.push(format!("{tx}"));
+            }
+        }
+
+        r
+    }
+
+    // Read the current ring buffer sizes for `nic` from `ethtool -g`
+    fn current(nic: &str) -> Result<RingBuffers, String> {
+        assert!(nic.bytes().all(|b| b.is_ascii_alphanumeric()));
+
+        let output = std::process::Command::new("/usr/sbin/ethtool")
+            .arg("-g")
+            .arg(nic)
+            .output()
+            .map_err(|e| format!("failed to execute process: {e}"))?;
+
+        Ok(Self::parse_current(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    // Parse the "Current hardware settings" section of `ethtool -g` output
+    fn parse_current(text: &str) -> RingBuffers {
+        let mut ring = RingBuffers::default();
+        let mut in_current_settings = false;
+
+        for line in text.lines() {
+            let line = line.trim();
+
+            if line.starts_with("Current hardware settings") {
+                in_current_settings = true;
+                continue;
+            }
+
+            if !in_current_settings {
+                continue;
+            }
+
+            if let Some(value) = line.strip_prefix("RX:") {
+                ring.rx = value.trim().parse().ok();
+            } else if let Some(value) = line.strip_prefix("TX:") {
+                ring.tx = value.trim().parse().ok();
+            }
+        }
+
+        ring
+    }
+
+    // Print the exact ethtool invocation this config would run, without running it
+    pub fn plan(&self, nic: &str) {
+        let args = self.args();
+
+        if args.is_empty() {
+            return;
+        }
+
+        println!("/usr/sbin/ethtool -G {nic} {}", args.join(" "));
+    }
+
+    // Read back the current ring buffer sizes and print only what would change
+    pub fn diff(&self, nic: &str) {
+        let current = match Self::current(nic) {
+            Ok(current) => current,
+            Err(e) => {
+                eprintln!("unable to read current ring buffer sizes for {nic}: {e}");
+                return;
+            }
+        };
+
+        if self.rx.is_some() && self.rx != current.rx {
+            println!("{nic} rx: {:?} -> {:?}", current.rx, self.rx);
+        }
+
+        if self.tx.is_some() && self.tx != current.tx {
+            println!("{nic} tx: {:?} -> {:?}", current.tx, self.tx);
+        }
+    }
+
+    // Turn the config into a set of args to pass to ethtool
+    fn args(&self) -> Vec<String> {
+        let mut r = Vec::new();
+
+        if let Some(rx) = self.rx {
+            r.push("rx".into());
+            r.push(format!("{rx}"));
+        }
+
+        if let Some(tx) = self.tx {
+            r.push("tx".into());
+            r.push(format!("{tx}"));
+        }
+
+        r
+    }
+}
+
+impl Display for RingBuffers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(f, "{}", self.args().join(" "))
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Coalescing {
+    adaptive_rx: Option<bool>,
+    rx_usecs: Option<usize>,
+}
+
+impl Coalescing {
+    // Apply the interrupt coalescing settings to the specified network
+    // interface, skipping fields whose current value already matches the target
+    pub fn apply(&self, nic: &str) {
+        assert!(nic.bytes().all(|b| b.is_ascii_alphanumeric()));
+
+        let args = self.changed_args(nic);
+
+        if args.is_empty() {
+            return;
+        }
+
+        std::process::Command::new("/usr/sbin/ethtool")
+            .arg("-C")
+            .arg(nic)
+            .args(args)
+            .output()
+            .expect("failed to execute process");
+    }
+
+    // Like `args()`, but only for fields whose current value differs from the target
+    fn changed_args(&self, nic: &str) -> Vec<String> {
+        let current = Self::current(nic).unwrap_or_default();
+        let mut r = Vec::new();
+
+        if let Some(adaptive_rx) = self.adaptive_rx {
+            if current.adaptive_rx != Some(adaptive_rx) {
+                r.push("adaptive-rx".into());
+                r.push(on_off(adaptive_rx));
+            }
+        }
+
+        if let Some(rx_usecs) = self.rx_usecs {
+            if current.rx_usecs != Some(rx_usecs) {
+                r.push("rx-usecs".into());
+                r.push(format!("{rx_usecs}"));
+            }
+        }
+
+        r
+    }
+
+    // Print the exact ethtool invocation this config would run, without running it
+    pub fn plan(&self, nic: &str) {
+        let args = self.args();
+
+        if args.is_empty() {
+            return;
+        }
+
+        println!("/usr/sbin/ethtool -C {nic} {}", args.join(" "));
+    }
+
+    // Read back the current coalescing settings and print only what would change
+    pub fn diff(&self, nic: &str) {
+        let current = match Self::current(nic) {
+            Ok(current) => current,
+            Err(e) => {
+                eprintln!("unable to read current coalescing settings for {nic}: {e}");
+                return;
+            }
+        };
+
+        if self.adaptive_rx.is_some() && self.adaptive_rx != current.adaptive_rx {
+            println!("{nic} adaptive-rx: {:?} -> {:?}", current.adaptive_rx, self.adaptive_rx);
+        }
+
+        if self.rx_usecs.is_some() && self.rx_usecs != current.rx_usecs {
+            println!("{nic} rx-usecs: {:?} -> {:?}", current.rx_usecs, self.rx_usecs);
+        }
+    }
+
+    // Read the current coalescing settings for `nic` from `ethtool -c`
+    fn current(nic: &str) -> Result<Coalescing, String> {
+        assert!(nic.bytes().all(|b| b.is_ascii_alphanumeric()));
+
+        let output = std::process::Command::new("/usr/sbin/ethtool")
+            .arg("-c")
+            .arg(nic)
+            .output()
+            .map_err(|e| format!("failed to execute process: {e}"))?;
+
+        Ok(Self::parse_current(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    // Parse the "Adaptive RX:"/"rx-usecs:" lines from `ethtool -c` output
+    fn parse_current(text: &str) -> Coalescing {
+        let mut coalescing = Coalescing::default();
+
+        for line in text.lines() {
+            let line = line.trim();
+
+            if let Some(rest) = line.strip_prefix("Adaptive RX:") {
+                coalescing.adaptive_rx = parse_on_off(rest.split_whitespace().next().unwrap_or(""));
+            } else if let Some(value) = line.strip_prefix("rx-usecs:") {
+                coalescing.rx_usecs = value.trim().parse().ok();
+            }
+        }
+
+        coalescing
+    }
+
+    // Turn the config into a set of args to pass to ethtool
+    fn args(&self) -> Vec<String> {
+        let mut r = Vec::new();
+
+        if let Some(adaptive_rx) = self.adaptive_rx {
+            r.push("adaptive-rx".into());
+            r.push(on_off(adaptive_rx));
+        }
+
+        if let Some(rx_usecs) = self.rx_usecs {
+            r.push("rx-usecs".into());
+            r.push(format!("{rx_usecs}"));
+        }
+
+        r
+    }
+}
+
+impl Display for Coalescing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(f, "{}", self.args().join(" "))
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Offloads {
+    gro: Option<bool>,
+    lro: Option<bool>,
+}
+
+impl Offloads {
+    // Apply the feature offload settings to the specified network interface,
+    // skipping fields whose current value already matches the target
+    pub fn apply(&self, nic: &str) {
+        assert!(nic.bytes().all(|b| b.is_ascii_alphanumeric()));
+
+        let args = self.changed_args(nic);
+
+        if args.is_empty() {
+            return;
+        }
+
+        std::process::Command::new("/usr/sbin/ethtool")
+            .arg("-K")
+            .arg(nic)
+            .args(args)
+            .output()
+            .expect("failed to execute process");
+    }
+
+    // Like `args()`, but only for fields whose current value differs from the target
+    fn changed_args(&self, nic: &str) -> Vec<String> {
+        let current = Self::current(nic).unwrap_or_default();
+        let mut r = Vec::new();
+
+        if let Some(gro) = self.gro {
+            if current.gro != Some(gro) {
+                r.push("gro".into());
+                r.push(on_off(gro));
+            }
+        }
+
+        if let Some(lro) = self.lro {
+            if current.lro != Some(lro) {
+                r.push("lro".into());
+                r.push(on_off(lro));
+            }
+        }
+
+        r
+    }
+
+    // Print the exact ethtool invocation this config would run, without running it
+    pub fn plan(&self, nic: &str) {
+        let args = self.args();
+
+        if args.is_empty() {
+            return;
+        }
+
+        println!("/usr/sbin/ethtool -K {nic} {}", args.join(" "));
+    }
+
+    // Read back the current offload settings and print only what would change
+    pub fn diff(&self, nic: &str) {
+        let current = match Self::current(nic) {
+            Ok(current) => current,
+            Err(e) => {
+                eprintln!("unable to read current offload settings for {nic}: {e}");
+                return;
+            }
+        };
+
+        if self.gro.is_some() && self.gro != current.gro {
+            println!("{nic} gro: {:?} -> {:?}", current.gro, self.gro);
+        }
+
+        if self.lro.is_some() && self.lro != current.lro {
+            println!("{nic} lro: {:?} -> {:?}", current.lro, self.lro);
+        }
+    }
+
+    // Read the current offload settings for `nic` from `ethtool -k`
+    fn current(nic: &str) -> Result<Offloads, String> {
+        assert!(nic.bytes().all(|b| b.is_ascii_alphanumeric()));
+
+        let output = std::process::Command::new("/usr/sbin/ethtool")
+            .arg("-k")
+            .arg(nic)
+            .output()
+            .map_err(|e| format!("failed to execute process: {e}"))?;
+
+        Ok(Self::parse_current(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    // Parse the "generic-receive-offload:"/"large-receive-offload:" lines
+    // from `ethtool -k` output
+    fn parse_current(text: &str) -> Offloads {
+        let mut offloads = Offloads::default();
+
+        for line in text.lines() {
+            let line = line.trim();
+
+            if let Some(rest) = line.strip_prefix("generic-receive-offload:") {
+                offloads.gro = parse_on_off(rest.split_whitespace().next().unwrap_or(""));
+            } else if let Some(rest) = line.strip_prefix("large-receive-offload:") {
+                offloads.lro = parse_on_off(rest.split_whitespace().next().unwrap_or(""));
+            }
+        }
+
+        offloads
+    }
+
+    // Turn the config into a set of args to pass to ethtool
+    fn args(&self) -> Vec<String> {
+        let mut r = Vec::new();
+
+        if let Some(gro) = self.gro {
+            r.push("gro".into());
+            r.push(on_off(gro));
+        }
+
+        if let Some(lro) = self.lro {
+            r.push("lro".into());
+            r.push(on_off(lro));
+        }
+
+        r
+    }
+}
+
+impl Display for Offloads {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(f, "{}", self.args().join(" "))
+    }
+}
+
+/// Steers packets to CPUs in software by writing hex CPU masks to each
+/// queue's `rps_cpus`/`xps_cpus`, rather than relying solely on IRQ affinity.
+#[derive(Deserialize, Default)]
+pub struct SoftwareSteering {
+    // CPU mask written to every rx queue's rps_cpus, e.g. "f"
+    rps_cpus: Option<String>,
+    // CPU mask written to every tx queue's xps_cpus
+    xps_cpus: Option<String>,
+}
+
+impl SoftwareSteering {
+    // Write the configured CPU masks to every matching queue's sysfs file,
+    // skipping any queue whose current mask already matches the target
+    pub fn apply(&self, nic: &str) {
+        if let Some(mask) = &self.rps_cpus {
+            for queue in Self::queues(nic, "rx") {
+                Self::write_mask_if_changed(&format!("/sys/class/net/{nic}/queues/{queue}/rps_cpus"), mask);
+            }
+        }
+
+        if let Some(mask) = &self.xps_cpus {
+            for queue in Self::queues(nic, "tx") {
+                Self::write_mask_if_changed(&format!("/sys/class/net/{nic}/queues/{queue}/xps_cpus"), mask);
+            }
+        }
+    }
+
+    // Print the sysfs writes this config would make, without making them
+    pub fn plan(&self, nic: &str) {
+        if let Some(mask) = &self.rps_cpus {
+            for queue in Self::queues(nic, "rx") {
+                println!("write \"{mask}\" to /sys/class/net/{nic}/queues/{queue}/rps_cpus");
+            }
+        }
+
+        if let Some(mask) = &self.xps_cpus {
+            for queue in Self::queues(nic, "tx") {
+                println!("write \"{mask}\" to /sys/class/net/{nic}/queues/{queue}/xps_cpus");
+            }
+        }
+    }
+
+    // Read back the current rps_cpus/xps_cpus masks and print only what would change
+    pub fn diff(&self, nic: &str) {
+        if let Some(mask) = &self.rps_cpus {
+            for (path, current) in Self::current(nic, "rx", "rps_cpus") {
+                if &current != mask {
+                    println!("{path}: {current} -> {mask}");
+                }
+            }
+        }
+
+        if let Some(mask) = &self.xps_cpus {
+            for (path, current) in Self::current(nic, "tx", "xps_cpus") {
+                if &current != mask {
+                    println!("{path}: {current} -> {mask}");
+                }
+            }
+        }
+    }
+
+    // Read the current contents of every matching queue's `file` sysfs entry
+    fn current(nic: &str, prefix: &str, file: &str) -> Vec<(String, String)> {
+        Self::queues(nic, prefix)
+            .into_iter()
+            .map(|queue| {
+                let path = format!("/sys/class/net/{nic}/queues/{queue}/{file}");
+                let current = std::fs::read_to_string(&path).unwrap_or_default().trim().to_string();
+
+                (path, current)
+            })
+            .collect()
+    }
+
+    // List the rx-*/tx-* queue directories under /sys/class/net/<nic>/queues
+    fn queues(nic: &str, prefix: &str) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(format!("/sys/class/net/{nic}/queues")) else {
+            return Vec::new();
+        };
+
+        let mut queues: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .filter(|name| name.starts_with(&format!("{prefix}-")))
+            .collect();
+
+        queues.sort();
+
+        queues
+    }
+
+    fn write_mask_if_changed(path: &str, mask: &str) {
+        assert!(mask.bytes().all(|b| b.is_ascii_hexdigit() || b == b","[0]));
+
+        if std::fs::read_to_string(path).map(|s| s.trim() == mask).unwrap_or(false) {
+            return;
+        }
+
+        Self::write_mask(path, mask);
+    }
+
+    fn write_mask(path: &str, mask: &str) {
+        assert!(mask.bytes().all(|b| b.is_ascii_hexdigit() || b == b","[0]));
+
+        if let Ok(mut f) = std::fs::File::options()
+            .write(true)
+            .truncate(true)
+            .create(false)
+            .open(path)
+        {
+            if f.write_all(mask.as_bytes()).is_ok() {
+                return;
+            }
+        }
+
+        eprintln!("failed to write {mask} to {path}");
+    }
 }
\ No newline at end of file
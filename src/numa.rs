@@ -0,0 +1,110 @@
+//! Resolves the CPUs local to a NIC's NUMA node, for automatic IRQ affinity
+//! assignment.
+
+use std::collections::BTreeSet;
+
+/// Returns the NIC's NUMA node, or `None` if the driver doesn't report one
+/// (`numa_node` reads back `-1`).
+pub fn node(nic: &str) -> std::io::Result<Option<u32>> {
+    let contents = std::fs::read_to_string(format!("/sys/class/net/{nic}/device/numa_node"))?;
+    let node: i64 = contents.trim().parse().unwrap_or(-1);
+
+    Ok(if node < 0 { None } else { Some(node as u32) })
+}
+
+/// The CPUs local to `nic`'s NUMA node, falling back to all online CPUs when
+/// the node is unknown. When `physical_only` is set, SMT siblings are
+/// collapsed down to one representative CPU per physical core.
+pub fn local_cpus(nic: &str, physical_only: bool) -> std::io::Result<Vec<u32>> {
+    let cpus = match node(nic)? {
+        Some(node) => {
+            expand_cpulist(&std::fs::read_to_string(format!(
+                "/sys/devices/system/node/node{node}/cpulist"
+            ))?)
+        }
+        None => expand_cpulist(&std::fs::read_to_string(
+            "/sys/devices/system/cpu/online",
+        )?),
+    };
+
+    Ok(if physical_only {
+        physical_cores(&cpus)
+    } else {
+        cpus
+    })
+}
+
+/// Parses a Linux cpulist such as `0-3,8,10-11` into individual CPU numbers.
+fn expand_cpulist(list: &str) -> Vec<u32> {
+    let mut cpus = Vec::new();
+
+    for range in list.trim().split(',') {
+        if range.is_empty() {
+            continue;
+        }
+
+        match range.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.parse::<u32>(), end.parse::<u32>()) {
+                    cpus.extend(start..=end);
+                }
+            }
+            None => {
+                if let Ok(cpu) = range.parse() {
+                    cpus.push(cpu);
+                }
+            }
+        }
+    }
+
+    cpus
+}
+
+/// The CPUs that share a physical core with `cpu`, read from
+/// `/sys/devices/system/cpu/cpuN/topology/thread_siblings_list`. Falls back
+/// to just `cpu` itself if the topology file can't be read.
+fn thread_siblings(cpu: u32) -> Vec<u32> {
+    std::fs::read_to_string(format!(
+        "/sys/devices/system/cpu/cpu{cpu}/topology/thread_siblings_list"
+    ))
+    .map(|list| expand_cpulist(&list))
+    .unwrap_or_else(|_| vec![cpu])
+}
+
+// Keep one representative CPU per physical core: the lowest-numbered CPU in
+// each thread_siblings_list group, so SMT siblings are skipped. Collected
+// into a set keyed by the representative itself, so this doesn't depend on
+// `cpus` being in ascending order.
+fn physical_cores(cpus: &[u32]) -> Vec<u32> {
+    cpus.iter()
+        .map(|&cpu| thread_siblings(cpu).into_iter().min().unwrap_or(cpu))
+        .collect::<BTreeSet<u32>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_cpulist_parses_ranges_and_singles() {
+        assert_eq!(expand_cpulist("0-3,8,10-11"), vec![0, 1, 2, 3, 8, 10, 11]);
+    }
+
+    #[test]
+    fn expand_cpulist_parses_a_single_cpu() {
+        assert_eq!(expand_cpulist("5"), vec![5]);
+    }
+
+    #[test]
+    fn expand_cpulist_parses_a_single_range() {
+        assert_eq!(expand_cpulist("0-3"), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn expand_cpulist_handles_empty_input() {
+        assert_eq!(expand_cpulist(""), Vec::<u32>::new());
+        assert_eq!(expand_cpulist("\n"), Vec::<u32>::new());
+    }
+}